@@ -0,0 +1,299 @@
+//! Binary-lifting jump-pointer table over the parent forest.
+//!
+//! `SessionIndex::parent_of` only steps one link at a time, so walking from
+//! a deep edit back to its triggering human message is O(chain length), and
+//! there's no way to find the shared ancestor of two arbitrary lines short
+//! of walking both chains to the root. [`AncestorIndex`] precomputes, for
+//! every UUID, its 2^k-th ancestor for every `k` up to `log2(max_depth)`,
+//! which turns both of those into O(log n) operations.
+//!
+//! Built on demand from a finished [`SessionIndex`] (analogous to
+//! [`compute_diffs`](super::diff::compute_diffs)) rather than carried as a
+//! field on the index itself: appending lines can change a node's ancestor
+//! chain, so keeping the table in sync incrementally isn't worth it when
+//! rebuilding it from the (already O(1)-lookup) parent map is cheap.
+
+use std::collections::{HashMap, HashSet};
+
+use super::types::SessionIndex;
+
+/// Precomputed ancestor jump table over a session's parent forest.
+///
+/// Sessions can contain multiple detached/compacted chains; each is simply
+/// treated as its own tree rooted at whichever node has no indexed parent.
+pub struct AncestorIndex {
+    /// UUID → depth within its tree (root = 0).
+    depth: HashMap<String, u32>,
+    /// `up[k][uuid]` = the 2^k-th ancestor of `uuid`, when it exists.
+    up: Vec<HashMap<String, String>>,
+    /// UUID → line of the nearest ancestor (including itself) whose line is
+    /// in `human_message_lines`, memoized once at build time.
+    human_boundary: HashMap<String, Option<u32>>,
+}
+
+/// Build the ancestor index for `index`.
+pub fn build_ancestor_index(index: &SessionIndex) -> AncestorIndex {
+    let mut depth: HashMap<String, u32> = HashMap::new();
+    for uuid in index.uuid_to_line.keys() {
+        compute_depth(uuid, index, &mut depth);
+    }
+
+    let max_depth = depth.values().copied().max().unwrap_or(0);
+    let mut num_levels = 1;
+    while (1u32 << num_levels) <= max_depth {
+        num_levels += 1;
+    }
+
+    let mut up: Vec<HashMap<String, String>> = Vec::with_capacity(num_levels);
+    let mut level0 = HashMap::new();
+    for (uuid, parent) in &index.parent_map {
+        if index.uuid_to_line.contains_key(parent) {
+            level0.insert(uuid.clone(), parent.clone());
+        }
+    }
+    up.push(level0);
+    for k in 1..num_levels {
+        let prev = &up[k - 1];
+        let mut level = HashMap::with_capacity(prev.len());
+        for (v, mid) in prev {
+            if let Some(ancestor) = prev.get(mid) {
+                level.insert(v.clone(), ancestor.clone());
+            }
+        }
+        up.push(level);
+    }
+
+    let mut human_boundary: HashMap<String, Option<u32>> = HashMap::new();
+    for uuid in index.uuid_to_line.keys() {
+        compute_human_boundary(uuid, index, &up[0], &mut human_boundary);
+    }
+
+    AncestorIndex {
+        depth,
+        up,
+        human_boundary,
+    }
+}
+
+/// Fill in `depth` for `start` and every unresolved ancestor on its chain,
+/// walking iteratively so a long session's chain can't blow the stack.
+///
+/// Cycle detection uses a `HashSet` of the current walk's visited nodes
+/// rather than scanning the chain `Vec` — for a single long, unbranched
+/// chain (the common case with no edits/compaction), a per-step linear scan
+/// would make the whole index build quadratic in chain length.
+fn compute_depth(start: &str, index: &SessionIndex, depth: &mut HashMap<String, u32>) {
+    if depth.contains_key(start) {
+        return;
+    }
+
+    let mut chain = vec![start.to_string()];
+    let mut visited: HashSet<String> = HashSet::new();
+    visited.insert(start.to_string());
+    loop {
+        let current = chain.last().unwrap().clone();
+        let parent = index
+            .parent_map
+            .get(&current)
+            .filter(|p| index.uuid_to_line.contains_key(*p));
+        match parent {
+            Some(parent) if depth.contains_key(parent) => {
+                chain.push(parent.clone());
+                break;
+            }
+            Some(parent) if !visited.contains(parent) => {
+                visited.insert(parent.clone());
+                chain.push(parent.clone());
+            }
+            // No (indexed) parent, or a cycle: treat the chain's tip as a root.
+            _ => break,
+        }
+    }
+
+    let root = chain.last().unwrap().clone();
+    let mut base = *depth.entry(root.clone()).or_insert(0);
+    for uuid in chain.into_iter().rev().skip(1) {
+        base += 1;
+        depth.insert(uuid, base);
+    }
+}
+
+/// Fill in `memo` for `start` and every node on the way to the nearest
+/// human-message boundary (including `start` itself, if it qualifies).
+/// Cycle detection uses a `HashSet`, for the same reason as [`compute_depth`].
+fn compute_human_boundary(
+    start: &str,
+    index: &SessionIndex,
+    up0: &HashMap<String, String>,
+    memo: &mut HashMap<String, Option<u32>>,
+) {
+    if memo.contains_key(start) {
+        return;
+    }
+
+    let mut chain = vec![start.to_string()];
+    let mut visited: HashSet<String> = HashSet::new();
+    visited.insert(start.to_string());
+    let result = loop {
+        let current = chain.last().unwrap().clone();
+        if let Some(cached) = memo.get(&current) {
+            break *cached;
+        }
+        if let Some(line) = index.line_for_uuid(&current) {
+            if index.is_human_message(line) {
+                break Some(line);
+            }
+        }
+        match up0.get(&current) {
+            Some(parent) if !visited.contains(parent) => {
+                visited.insert(parent.clone());
+                chain.push(parent.clone());
+            }
+            _ => break None,
+        }
+    };
+
+    for uuid in chain {
+        memo.insert(uuid, result);
+    }
+}
+
+impl AncestorIndex {
+    /// The 2^k-th-decomposed ancestor of `uuid`, `steps` links up, in
+    /// O(log `steps`). Returns `None` if the chain runs out before then.
+    pub fn ancestor(&self, uuid: &str, steps: u32) -> Option<String> {
+        let mut current = uuid.to_string();
+        let mut remaining = steps;
+        let mut level = 0usize;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                let table = self.up.get(level)?;
+                current = table.get(&current)?.clone();
+            }
+            remaining >>= 1;
+            level += 1;
+        }
+        Some(current)
+    }
+
+    /// The line of the nearest ancestor of `uuid` (including `uuid` itself)
+    /// that is a human-message boundary, or `None` if the chain has none.
+    pub fn human_boundary_of(&self, uuid: &str) -> Option<u32> {
+        self.human_boundary.get(uuid).copied().flatten()
+    }
+
+    /// The lowest common ancestor of `a` and `b`, or `None` if either UUID
+    /// is unknown or they belong to disconnected trees.
+    pub fn lca(&self, a: &str, b: &str) -> Option<String> {
+        let depth_a = *self.depth.get(a)?;
+        let depth_b = *self.depth.get(b)?;
+
+        let (mut hi, mut lo, hi_depth, lo_depth) = if depth_a >= depth_b {
+            (a.to_string(), b.to_string(), depth_a, depth_b)
+        } else {
+            (b.to_string(), a.to_string(), depth_b, depth_a)
+        };
+
+        hi = self.ancestor(&hi, hi_depth - lo_depth)?;
+        if hi == lo {
+            return Some(hi);
+        }
+
+        for level in (0..self.up.len()).rev() {
+            let table = &self.up[level];
+            if let (Some(next_hi), Some(next_lo)) = (table.get(&hi), table.get(&lo)) {
+                if next_hi != next_lo {
+                    hi = next_hi.clone();
+                    lo = next_lo.clone();
+                }
+            }
+        }
+
+        let parent_hi = self.up[0].get(&hi)?;
+        let parent_lo = self.up[0].get(&lo)?;
+        if parent_hi == parent_lo {
+            Some(parent_hi.clone())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a `SessionIndex` with just the fields `ancestors` needs: a
+    /// chain of `(uuid, parent_uuid)` pairs assigned sequential lines, and
+    /// which of those lines are human-message boundaries.
+    fn index_from_edges(edges: &[(&str, Option<&str>)], human: &[&str]) -> SessionIndex {
+        let mut index = SessionIndex::empty();
+        for (line, (uuid, parent)) in edges.iter().enumerate() {
+            index.uuid_to_line.insert(uuid.to_string(), line as u32);
+            if let Some(parent) = parent {
+                index.parent_map.insert(uuid.to_string(), parent.to_string());
+            }
+            if human.contains(uuid) {
+                index.human_message_lines.push(line as u32);
+            }
+        }
+        index.human_message_lines.sort_unstable();
+        index
+    }
+
+    #[test]
+    fn lca_within_a_single_chain() {
+        // root -> a -> b -> c
+        //              \-> d
+        let index = index_from_edges(
+            &[
+                ("root", None),
+                ("a", Some("root")),
+                ("b", Some("a")),
+                ("c", Some("b")),
+                ("d", Some("b")),
+            ],
+            &[],
+        );
+        let ancestors = build_ancestor_index(&index);
+
+        assert_eq!(ancestors.lca("c", "d"), Some("b".to_string()));
+        assert_eq!(ancestors.lca("c", "c"), Some("c".to_string()));
+        assert_eq!(ancestors.ancestor("c", 2), Some("a".to_string()));
+        assert_eq!(ancestors.ancestor("c", 10), None);
+    }
+
+    #[test]
+    fn lca_across_disconnected_trees_is_none() {
+        // Two detached chains, e.g. either side of a compaction boundary.
+        let index = index_from_edges(
+            &[
+                ("root-1", None),
+                ("a", Some("root-1")),
+                ("root-2", None),
+                ("b", Some("root-2")),
+            ],
+            &[],
+        );
+        let ancestors = build_ancestor_index(&index);
+
+        assert_eq!(ancestors.lca("a", "b"), None);
+        assert_eq!(ancestors.lca("root-1", "root-2"), None);
+    }
+
+    #[test]
+    fn human_boundary_of_climbs_to_nearest_human_ancestor() {
+        let index = index_from_edges(
+            &[
+                ("prompt", None),
+                ("assistant-1", Some("prompt")),
+                ("edit", Some("assistant-1")),
+            ],
+            &["prompt"],
+        );
+        let ancestors = build_ancestor_index(&index);
+
+        assert_eq!(ancestors.human_boundary_of("edit"), Some(0));
+        assert_eq!(ancestors.human_boundary_of("prompt"), Some(0));
+    }
+}
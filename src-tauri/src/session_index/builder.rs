@@ -4,13 +4,14 @@
 
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 use crate::claude_code::{FileEdit, FileEditType};
 
+use super::pattern::index_hot_patterns;
+use super::text_index::{index_line, searchable_segments};
 use super::types::{EditMetadata, SessionIndex};
 
 /// Build a complete session index from a JSONL file.
@@ -37,11 +38,6 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
         .modified()
         .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
 
-    // Track file edits (need to determine added vs modified)
-    let mut file_operations: HashMap<String, FileEditType> = HashMap::new();
-    let mut files_with_prior_content: HashSet<String> = HashSet::new();
-    let mut file_timestamps: HashMap<String, String> = HashMap::new();
-
     let mut byte_offset: u64 = 0;
 
     for (sequence, line_result) in reader.lines().enumerate() {
@@ -72,6 +68,25 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
                 index.human_message_lines.push(seq);
             }
 
+            // Index the timestamp, if present and parseable.
+            if let Some(ts) = entry.timestamp.as_deref().and_then(parse_timestamp_millis) {
+                index.timestamp_index.push((ts, seq));
+            }
+
+            // Index searchable text (user/assistant message text, tool-call
+            // file paths) for this line.
+            if let Some(ref message) = entry.message {
+                if let Some(ref content) = message.content {
+                    let segments = searchable_segments(entry.entry_type.as_deref(), content);
+                    index_line(&mut index, seq, &segments);
+                }
+            }
+
+            // Pre-index the small set of "hot" JSON patterns for O(1) lookup.
+            if let Ok(raw_value) = serde_json::from_str::<Value>(&line) {
+                index_hot_patterns(&mut index, seq, &raw_value);
+            }
+
             // Extract file edits from assistant messages
             if entry.entry_type.as_deref() == Some("assistant") {
                 if let Some(ref message) = entry.message {
@@ -87,9 +102,6 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
                                     entry.parent_uuid.as_deref(),
                                     entry.timestamp.as_deref(),
                                     &mut index,
-                                    &mut file_operations,
-                                    &mut files_with_prior_content,
-                                    &mut file_timestamps,
                                 );
                             }
                         }
@@ -102,19 +114,26 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
     }
 
     // Build final file edits list
-    finalize_file_edits(
-        &mut index,
-        file_operations,
-        files_with_prior_content,
-        file_timestamps,
-    );
+    finalize_file_edits(&mut index);
 
     // Sort human message lines for binary search
     index.human_message_lines.sort();
 
+    // Timestamps are expected to be monotonic within a single pass, but
+    // sort defensively so `lines_in_time_range` can always bisect safely.
+    index.timestamp_index.sort_by_key(|(ts, _)| *ts);
+
     Ok(index)
 }
 
+/// Parse an RFC3339 timestamp into epoch milliseconds, used to build the
+/// [`SessionIndex::timestamp_index`].
+pub(crate) fn parse_timestamp_millis(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.timestamp_millis())
+}
+
 /// Check if an entry is a human message (actual user input, not tool results).
 fn is_human_message(entry: &JsonEntry) -> bool {
     // Must be a user message
@@ -155,7 +174,13 @@ fn is_human_message(entry: &JsonEntry) -> bool {
 }
 
 /// Process a potential tool_use entry for file edits.
-fn process_tool_use(
+///
+/// Writes directly into `index`'s retained `file_operations` /
+/// `files_with_prior_content` / `file_timestamps` accumulators (rather than
+/// local variables that get discarded after this pass) so that an
+/// incremental tail-append update can re-run [`finalize_file_edits`] with
+/// exactly the same inputs a full rebuild would have produced.
+pub(crate) fn process_tool_use(
     item: &Value,
     project_path: &str,
     sequence: u32,
@@ -164,9 +189,6 @@ fn process_tool_use(
     _parent_uuid: Option<&str>,
     timestamp: Option<&str>,
     index: &mut SessionIndex,
-    file_operations: &mut HashMap<String, FileEditType>,
-    files_with_prior_content: &mut HashSet<String>,
-    file_timestamps: &mut HashMap<String, String>,
 ) {
     // Check if this is a tool_use
     if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
@@ -191,16 +213,18 @@ fn process_tool_use(
                 // Check if this edit has old_string content (indicates existing file)
                 if let Some(old_str) = input.get("old_string").and_then(|v| v.as_str()) {
                     if !old_str.is_empty() {
-                        files_with_prior_content.insert(rel_path.clone());
+                        index.files_with_prior_content.insert(rel_path.clone());
                     }
                 }
 
                 // Mark as modified
-                file_operations.insert(rel_path.clone(), FileEditType::Modified);
+                index
+                    .file_operations
+                    .insert(rel_path.clone(), FileEditType::Modified);
 
                 // Track timestamp
                 if let Some(ts) = timestamp {
-                    file_timestamps.insert(rel_path.clone(), ts.to_string());
+                    index.file_timestamps.insert(rel_path.clone(), ts.to_string());
                 }
 
                 // Record edit metadata
@@ -224,13 +248,15 @@ fn process_tool_use(
                 let rel_path = make_relative_path(file_path, project_path);
 
                 // Write to a file that wasn't previously edited = added
-                if !file_operations.contains_key(&rel_path) {
-                    file_operations.insert(rel_path.clone(), FileEditType::Added);
+                if !index.file_operations.contains_key(&rel_path) {
+                    index
+                        .file_operations
+                        .insert(rel_path.clone(), FileEditType::Added);
                 }
 
                 // Track timestamp
                 if let Some(ts) = timestamp {
-                    file_timestamps.insert(rel_path.clone(), ts.to_string());
+                    index.file_timestamps.insert(rel_path.clone(), ts.to_string());
                 }
 
                 // Record edit metadata
@@ -253,23 +279,26 @@ fn process_tool_use(
     }
 }
 
-/// Finalize file edits list, determining added vs modified.
-fn finalize_file_edits(
-    index: &mut SessionIndex,
-    file_operations: HashMap<String, FileEditType>,
-    files_with_prior_content: HashSet<String>,
-    file_timestamps: HashMap<String, String>,
-) {
-    let mut edits: Vec<FileEdit> = file_operations
-        .into_iter()
-        .map(|(path, mut edit_type)| {
+/// Recompute `index.file_edits` from the retained `file_operations` /
+/// `files_with_prior_content` / `file_timestamps` accumulators, determining
+/// added vs modified. Safe to call after either a full build or an
+/// incremental update, since both maintain the same accumulators.
+pub(crate) fn finalize_file_edits(index: &mut SessionIndex) {
+    let mut edits: Vec<FileEdit> = index
+        .file_operations
+        .iter()
+        .map(|(path, &edit_type)| {
             // If a file was written but never had prior content, it's "added"
-            if edit_type == FileEditType::Modified && !files_with_prior_content.contains(&path) {
-                edit_type = FileEditType::Added;
-            }
-            let last_edited_at = file_timestamps.get(&path).cloned();
+            let edit_type = if edit_type == FileEditType::Modified
+                && !index.files_with_prior_content.contains(path)
+            {
+                FileEditType::Added
+            } else {
+                edit_type
+            };
+            let last_edited_at = index.file_timestamps.get(path).cloned();
             FileEdit {
-                path,
+                path: path.clone(),
                 edit_type,
                 last_edited_at,
             }
@@ -282,7 +311,7 @@ fn finalize_file_edits(
 }
 
 /// Convert an absolute file path to a relative path from the project root.
-fn make_relative_path(file_path: &str, project_path: &str) -> String {
+pub(crate) fn make_relative_path(file_path: &str, project_path: &str) -> String {
     let project = project_path.trim_end_matches('/');
     if file_path.starts_with(project) {
         file_path[project.len()..]
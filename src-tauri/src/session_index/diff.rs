@@ -0,0 +1,225 @@
+//! Structured diff hunks for file edits in an [`EditContext`](super::EditContext).
+//!
+//! `get_edit_context` returns the raw `SessionEvent`s for a conversation
+//! segment, which leaves the frontend to reconstruct what a given `Edit` or
+//! `Write` tool call actually changed. This module walks the same line range
+//! and turns each edit's `old_string`/`new_string` (or, for `Write`, the
+//! whole file content) into grouped, line-oriented hunks using `similar`.
+
+use serde::Serialize;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use super::builder::make_relative_path;
+use super::types::SessionIndex;
+
+/// Number of unchanged lines to keep around a change when grouping hunks.
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// A single tagged line within a hunk.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub content: String,
+}
+
+/// Tag for a line within a diff hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffTag {
+    Delete,
+    Insert,
+    Equal,
+}
+
+/// A contiguous group of changed (plus surrounding context) lines.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 0-based start line in the old content.
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// 0-based start line in the new content.
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// All hunks produced by a single `Edit`/`Write` tool call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// Sequence number of the event this edit came from.
+    pub line: u32,
+    /// Project-relative file path.
+    pub path: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Compute a [`FileDiff`] for every `Edit`/`Write` tool_use found among
+/// `lines` in `session_file`.
+pub fn compute_diffs(
+    index: &SessionIndex,
+    session_file: &Path,
+    project_path: &str,
+    lines: &[u32],
+) -> Result<Vec<FileDiff>, String> {
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut diffs = Vec::new();
+
+    for &line in lines {
+        // Only lines we already recognize as edits carry a tool_use worth
+        // diffing; skip everything else without re-parsing it.
+        if !index.edit_metadata.contains_key(&line) {
+            continue;
+        }
+        let Some((offset, _)) = index.line_offsets.get(line as usize).copied() else {
+            continue;
+        };
+        let Some(raw) = read_raw_line(&mut file, offset)? else {
+            continue;
+        };
+        diffs.extend(diffs_for_line(&raw, project_path, line));
+    }
+
+    Ok(diffs)
+}
+
+fn read_raw_line(file: &mut File, offset: u64) -> Result<Option<String>, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut reader = BufReader::new(&*file);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read line: {}", e))?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+    }
+    if line.ends_with('\r') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+fn diffs_for_line(raw: &str, project_path: &str, sequence: u32) -> Vec<FileDiff> {
+    let Ok(entry) = serde_json::from_str::<Value>(raw) else {
+        return Vec::new();
+    };
+
+    let Some(Value::Array(items)) = entry.pointer("/message/content") else {
+        return Vec::new();
+    };
+
+    let mut diffs = Vec::new();
+    for item in items {
+        if item.get("type").and_then(Value::as_str) != Some("tool_use") {
+            continue;
+        }
+        let name = item.get("name").and_then(Value::as_str);
+        let Some(input) = item.get("input") else {
+            continue;
+        };
+        let Some(file_path) = input.get("file_path").and_then(Value::as_str) else {
+            continue;
+        };
+        let path = make_relative_path(file_path, project_path);
+
+        let (old, new) = match name {
+            Some("Edit") => (
+                input
+                    .get("old_string")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                input
+                    .get("new_string")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+            ),
+            Some("Write") => (
+                String::new(),
+                input
+                    .get("content")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+            ),
+            _ => continue,
+        };
+
+        diffs.push(FileDiff {
+            line: sequence,
+            path,
+            hunks: hunks_for(&old, &new, DEFAULT_CONTEXT_LINES),
+        });
+    }
+    diffs
+}
+
+/// Diff `old` against `new` line-by-line, grouping changes into hunks with
+/// `context` lines of surrounding, unchanged context.
+fn hunks_for(old: &str, new: &str, context: usize) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+
+    for group in diff.grouped_ops(context) {
+        let mut lines = Vec::new();
+        let (mut old_start, mut new_start) = (usize::MAX, usize::MAX);
+        let (mut old_end, mut new_end) = (0, 0);
+
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let tag = match change.tag() {
+                    ChangeTag::Delete => DiffTag::Delete,
+                    ChangeTag::Insert => DiffTag::Insert,
+                    ChangeTag::Equal => DiffTag::Equal,
+                };
+                if let Some(idx) = change.old_index() {
+                    old_start = old_start.min(idx);
+                    old_end = old_end.max(idx + 1);
+                }
+                if let Some(idx) = change.new_index() {
+                    new_start = new_start.min(idx);
+                    new_end = new_end.max(idx + 1);
+                }
+                lines.push(DiffLine {
+                    tag,
+                    content: change.value().trim_end_matches('\n').to_string(),
+                });
+            }
+        }
+
+        if lines.is_empty() {
+            continue;
+        }
+
+        hunks.push(DiffHunk {
+            old_start: if old_start == usize::MAX { 0 } else { old_start },
+            old_lines: old_end.saturating_sub(if old_start == usize::MAX {
+                0
+            } else {
+                old_start
+            }),
+            new_start: if new_start == usize::MAX { 0 } else { new_start },
+            new_lines: new_end.saturating_sub(if new_start == usize::MAX {
+                0
+            } else {
+                new_start
+            }),
+            lines,
+        });
+    }
+
+    hunks
+}
@@ -11,12 +11,20 @@
 //! - O(1) file edit retrieval
 //! - O(k) parent chain walking (for edit context)
 //! - Pre-computed line offsets for fast pagination
+//! - A `<session>.idx` on-disk cache so repeat opens skip the full scan
+//! - O(log n) time-range lookups via a sorted timestamp index
+//! - Full-text search over message content and edited file paths, ranked
+//!   by BM25 relevance
+//! - Generic JSON path pattern matching for fields the index doesn't
+//!   otherwise hard-code (e.g. `message.content[].type == "thinking"`)
+//! - O(log n) ancestor/lowest-common-ancestor queries over the parent
+//!   forest via a binary-lifting jump table
 //!
 //! ## Usage
 //!
 //! ```ignore
-//! // Build index for a session
-//! let index = build_session_index(&session_file, &project_path)?;
+//! // Build (or load from cache) the index for a session
+//! let index = load_or_build_index(&session_file, &project_path)?;
 //!
 //! // Get status for frontend
 //! let status = index.to_status();
@@ -26,17 +34,30 @@
 //!     // Read event at that line
 //! }
 //!
-//! // Get edit context
-//! let context = get_edit_context(&index, &session_file, edit_line)?;
+//! // Get edit context, including structured diff hunks
+//! let context = get_edit_context(&index, &session_file, &project_path, edit_line)?;
 //! ```
 
+mod ancestors;
 mod builder;
+mod diff;
+mod pattern;
+mod persistence;
 mod queries;
+mod text_index;
 mod types;
 mod updater;
+mod watcher;
 
 // Re-export public API
+pub use ancestors::{build_ancestor_index, AncestorIndex};
 pub use builder::build_session_index;
-pub use queries::{get_edit_context, EditContext};
+pub use diff::{DiffHunk, DiffLine, DiffTag, FileDiff};
+pub use pattern::{find_by_pattern, Pattern, PatternMatch, HOT_PATTERNS};
+pub use persistence::{cache_path, load_or_build_index, save_index, CacheLoadError};
+pub use queries::{
+    get_edit_context, line_nearest_time, lines_in_time_range, search, EditContext, SearchHit,
+};
 pub use types::{IndexStatus, SessionIndex};
-pub use updater::{update_index_incremental, UpdateResult};
+pub use updater::{update_index_incremental, update_session_index, UpdateResult};
+pub use watcher::{SessionWatcher, WatchUpdate};
@@ -0,0 +1,257 @@
+//! Generic JSON path pattern matching over indexed session lines.
+//!
+//! `get_edit_context` and the rest of the index only understand a fixed set
+//! of fields (`uuid`, `parentUuid`, tool names). This module adds a small
+//! pattern language over arbitrary JSON paths, e.g.
+//! `message.content[].type == "thinking"` or `toolUseResult.*`, so callers
+//! can query fields the index doesn't hard-code support for.
+//!
+//! A compiled [`Pattern`] is a list of path segments (object keys, `[]`
+//! array wildcards, or a bare `*` wildcard) optionally terminated by an
+//! `== <literal>` equality check. Evaluating a pattern against a line's
+//! parsed `Value` walks those segments and returns every value it reaches,
+//! keyed by the concrete path it took (e.g. `content[2].type`) so matches
+//! can be inspected without re-walking the JSON.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use super::types::SessionIndex;
+
+/// Patterns pre-indexed into `SessionIndex::hot_patterns` at build time so
+/// common lookups resolve without a line-by-line scan.
+pub const HOT_PATTERNS: &[&str] = &[
+    r#"message.content[].type == "thinking""#,
+    r#"toolUseResult.is_error == true"#,
+];
+
+/// A single segment of a compiled path.
+#[derive(Debug, Clone)]
+enum PathSegment {
+    /// A literal object key.
+    Key(String),
+    /// `[]` or bare `*`: visit every element of an array, or every value of
+    /// an object.
+    Wildcard,
+}
+
+/// A compiled pattern: a path plus an optional equality predicate.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    segments: Vec<PathSegment>,
+    expected: Option<Value>,
+}
+
+/// A single line matching a [`Pattern`], with the concrete sub-values the
+/// path walk passed through.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternMatch {
+    pub line: u32,
+    /// Concrete path (e.g. `content[2].type`) → the value found there.
+    pub bindings: HashMap<String, Value>,
+}
+
+impl Pattern {
+    /// Compile a pattern string such as `message.content[].type == "thinking"`
+    /// or `toolUseResult.*`.
+    pub fn compile(pattern: &str) -> Result<Pattern, String> {
+        let (path_part, expected) = match pattern.split_once("==") {
+            Some((path, rhs)) => (path.trim(), Some(parse_literal(rhs.trim()))),
+            None => (pattern.trim(), None),
+        };
+
+        let mut segments = Vec::new();
+        for raw in path_part.split('.') {
+            let raw = raw.trim();
+            if raw.is_empty() {
+                continue;
+            }
+            if raw == "*" {
+                segments.push(PathSegment::Wildcard);
+                continue;
+            }
+            if let Some(key) = raw.strip_suffix("[]") {
+                segments.push(PathSegment::Key(key.to_string()));
+                segments.push(PathSegment::Wildcard);
+            } else {
+                segments.push(PathSegment::Key(raw.to_string()));
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(format!("pattern '{}' has no path segments", pattern));
+        }
+
+        Ok(Pattern { segments, expected })
+    }
+
+    /// Walk `root` and collect every reachable value, keyed by the concrete
+    /// path taken to reach it.
+    fn eval(&self, root: &Value) -> HashMap<String, Value> {
+        let mut bindings = HashMap::new();
+        walk(root, &self.segments, String::new(), &mut bindings);
+        bindings
+    }
+
+    /// Whether `root` satisfies this pattern: at least one reachable value
+    /// matches `expected` (or, with no predicate, at least one value is
+    /// reachable at all).
+    fn matches(&self, root: &Value) -> Option<HashMap<String, Value>> {
+        let bindings = self.eval(root);
+        if bindings.is_empty() {
+            return None;
+        }
+        match &self.expected {
+            None => Some(bindings),
+            Some(expected) => {
+                if bindings.values().any(|v| v == expected) {
+                    Some(bindings)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn walk(value: &Value, segments: &[PathSegment], path: String, bindings: &mut HashMap<String, Value>) {
+    let Some((first, rest)) = segments.split_first() else {
+        bindings.insert(path, value.clone());
+        return;
+    };
+
+    match first {
+        PathSegment::Key(key) => {
+            if let Some(child) = value.get(key) {
+                let next_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                walk(child, rest, next_path, bindings);
+            }
+        }
+        PathSegment::Wildcard => match value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk(item, rest, format!("{}[{}]", path, i), bindings);
+                }
+            }
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let next_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    walk(child, rest, next_path, bindings);
+                }
+            }
+            _ => {}
+        },
+    }
+}
+
+fn parse_literal(raw: &str) -> Value {
+    if let Some(inner) = raw.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Value::String(inner.to_string());
+    }
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Find every line matching `pattern`, streaming line-by-line from
+/// `session_file` via `index.line_offsets` so matching never holds the
+/// whole session in memory.
+pub fn find_by_pattern(
+    index: &SessionIndex,
+    session_file: &Path,
+    pattern: &str,
+) -> Result<Vec<PatternMatch>, String> {
+    let compiled = Pattern::compile(pattern)?;
+
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    // Hot path: this exact pattern was pre-indexed at build time, so we
+    // already know which lines match — but bindings still have to be
+    // recomputed from the line's value, same as the cold path, so callers
+    // get the same `PatternMatch` contract either way.
+    if let Some(lines) = index.hot_patterns.get(pattern) {
+        let mut matches = Vec::with_capacity(lines.len());
+        for &line in lines {
+            let Some(&(offset, _)) = index.line_offsets.get(line as usize) else {
+                continue;
+            };
+            let Some(raw) = read_raw_line(&mut file, offset)? else {
+                continue;
+            };
+            let Ok(value) = serde_json::from_str::<Value>(&raw) else {
+                continue;
+            };
+            if let Some(bindings) = compiled.matches(&value) {
+                matches.push(PatternMatch { line, bindings });
+            }
+        }
+        return Ok(matches);
+    }
+
+    let mut matches = Vec::new();
+    for (line, &(offset, _)) in index.line_offsets.iter().enumerate() {
+        let Some(raw) = read_raw_line(&mut file, offset)? else {
+            continue;
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&raw) else {
+            continue;
+        };
+        if let Some(bindings) = compiled.matches(&value) {
+            matches.push(PatternMatch {
+                line: line as u32,
+                bindings,
+            });
+        }
+    }
+
+    Ok(matches)
+}
+
+fn read_raw_line(file: &mut File, offset: u64) -> Result<Option<String>, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut reader = BufReader::new(&*file);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read line: {}", e))?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+    }
+    if line.ends_with('\r') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+/// Evaluate every pattern in [`HOT_PATTERNS`] against `entry` and record
+/// `sequence` under each one that matches. Called from the builder (and
+/// incremental updater) for every line as it's parsed.
+pub(crate) fn index_hot_patterns(index: &mut SessionIndex, sequence: u32, entry: &Value) {
+    for &pattern in HOT_PATTERNS {
+        let Ok(compiled) = Pattern::compile(pattern) else {
+            continue;
+        };
+        if compiled.matches(entry).is_some() {
+            index
+                .hot_patterns
+                .entry(pattern.to_string())
+                .or_default()
+                .push(sequence);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! On-disk cache for the session index.
+//!
+//! Session JSONL files can grow to tens of thousands of lines; re-scanning
+//! them from byte 0 every time a session is opened is wasteful. This module
+//! serializes a [`SessionIndex`] to a small sidecar file next to the session
+//! (`<session>.idx`) so a later open can load the cache and hand it straight
+//! to [`update_index_incremental`](super::update_index_incremental), which
+//! only has to parse whatever was appended since the cache was written.
+//!
+//! The cache is prefixed with a magic number and a format version so a
+//! schema change (new field on `SessionIndex`/`EditMetadata`) can migrate an
+//! old cache forward instead of discarding it outright.
+//!
+//! [`load_or_build_index`] is the self-healing entry point most callers
+//! want: missing/corrupt/stale caches are silently rebuilt. [`SessionIndex::
+//! save`]/[`SessionIndex::load`] are the lower-level, strict primitives for
+//! callers that want to handle a stale-or-unsupported cache themselves.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::builder::build_session_index;
+use super::types::SessionIndex;
+use super::updater::update_index_incremental;
+
+/// Magic number prefixing every cache file; guards against treating an
+/// unrelated file as an index cache.
+const CACHE_MAGIC: u32 = 0x5345_5849; // "SEXI"
+
+/// Current on-disk schema version. Bump this and add a `migrate_vN_to_vN+1`
+/// step below whenever `SessionIndex`'s shape changes.
+const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Versioned envelope written to the `.idx` sidecar file.
+#[derive(Serialize, Deserialize)]
+struct CacheEnvelope {
+    magic: u32,
+    version: u32,
+    payload: serde_json::Value,
+}
+
+/// Derive the sidecar cache path for a session file (`<session>.idx`).
+pub fn cache_path(session_file: &Path) -> PathBuf {
+    let mut path = session_file.as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+/// Load the index for `session_file`, preferring the on-disk cache.
+///
+/// [`SessionIndex::load`] only rejects the cache outright (`Stale`) when the
+/// live file shrank, was rewritten in place without changing size, or grew
+/// without its `last_modified` advancing — never when it simply grew (with a
+/// newer mtime), which is the common case this cache exists for. A
+/// cache that passes is handed to [`update_index_incremental`], so opening a
+/// session that grew since the cache was written only costs parsing the
+/// appended lines, not a full rescan. Anything that doesn't pass — missing,
+/// corrupt, an unrecognized version, or a rewritten file — triggers a full
+/// rebuild instead. Either way the result is written back to the cache
+/// before returning.
+pub fn load_or_build_index(
+    session_file: &Path,
+    project_path: &str,
+) -> Result<SessionIndex, String> {
+    let index = match SessionIndex::load(&cache_path(session_file), session_file) {
+        Ok(mut cached) => match update_index_incremental(&mut cached, session_file, project_path)
+        {
+            Ok(_) => cached,
+            Err(_) => build_session_index(session_file, project_path)?,
+        },
+        Err(_) => build_session_index(session_file, project_path)?,
+    };
+
+    save_index(&index, session_file);
+    Ok(index)
+}
+
+/// Dispatch to the decoder (and any migration chain) for the cached
+/// version. Unknown versions return `UnsupportedVersion`; a known version
+/// whose payload fails to decode returns `Io`.
+fn decode_payload(version: u32, payload: serde_json::Value) -> Result<SessionIndex, CacheLoadError> {
+    match version {
+        INDEX_FORMAT_VERSION => {
+            serde_json::from_value(payload).map_err(|e| CacheLoadError::Io(e.to_string()))
+        }
+        // A future schema bump would look like:
+        //   1 => serde_json::from_value::<v1::SessionIndexV1>(payload)
+        //       .map(migrate_v1_to_v2)
+        //       .map_err(|e| CacheLoadError::Io(e.to_string())),
+        _ => Err(CacheLoadError::UnsupportedVersion(version)),
+    }
+}
+
+/// Write the index to its sidecar cache file. Best-effort: a failure to
+/// persist the cache must never fail the caller's request for the index.
+pub fn save_index(index: &SessionIndex, session_file: &Path) {
+    let _ = index.save(&cache_path(session_file));
+}
+
+/// Why [`SessionIndex::load`] could not return a usable cached index.
+#[derive(Debug)]
+pub enum CacheLoadError {
+    /// The cache file couldn't be read or deserialized at all.
+    Io(String),
+    /// The envelope's version tag has no decoder or migration path.
+    UnsupportedVersion(u32),
+    /// The cache is well-formed but can't be trusted as a base for an
+    /// incremental update: the live file shrank below the cached
+    /// `file_size` (truncation/rebuild), or it's the same size but its
+    /// `last_modified` changed (an in-place rewrite rather than an append),
+    /// or it grew without `last_modified` advancing (inconsistent metadata).
+    Stale,
+}
+
+impl std::fmt::Display for CacheLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheLoadError::Io(e) => write!(f, "failed to read index cache: {}", e),
+            CacheLoadError::UnsupportedVersion(v) => {
+                write!(f, "index cache has unsupported version {}", v)
+            }
+            CacheLoadError::Stale => {
+                write!(f, "index cache is stale relative to the session file")
+            }
+        }
+    }
+}
+
+impl SessionIndex {
+    /// Persist this index to `path` (typically [`cache_path`] of the
+    /// session file it was built from).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let payload =
+            serde_json::to_value(self).map_err(|e| format!("Failed to serialize index: {}", e))?;
+        let envelope = CacheEnvelope {
+            magic: CACHE_MAGIC,
+            version: INDEX_FORMAT_VERSION,
+            payload,
+        };
+        let bytes = serde_json::to_vec(&envelope)
+            .map_err(|e| format!("Failed to encode index cache: {}", e))?;
+        fs::write(path, bytes).map_err(|e| format!("Failed to write index cache: {}", e))
+    }
+
+    /// Load a cached index from `path`, validating it against the live
+    /// `session_file`. Unlike [`load_or_build_index`], this never falls
+    /// back to a rebuild itself — it returns a typed error so the caller
+    /// can decide (e.g. rebuild, or surface a warning).
+    ///
+    /// A successful load doesn't mean the cache is fully current — the file
+    /// may have grown since it was written — only that it's safe to hand to
+    /// [`update_index_incremental`] to catch up the appended lines. It's
+    /// rejected as `Stale` when that wouldn't be safe: the file shrank, or
+    /// is the same size but was rewritten (different `last_modified`), or
+    /// grew without `last_modified` advancing.
+    pub fn load(path: &Path, session_file: &Path) -> Result<SessionIndex, CacheLoadError> {
+        let bytes = fs::read(path).map_err(|e| CacheLoadError::Io(e.to_string()))?;
+        let envelope: CacheEnvelope =
+            serde_json::from_slice(&bytes).map_err(|e| CacheLoadError::Io(e.to_string()))?;
+
+        if envelope.magic != CACHE_MAGIC {
+            return Err(CacheLoadError::Io("bad magic number".to_string()));
+        }
+
+        let index = decode_payload(envelope.version, envelope.payload)?;
+
+        let metadata =
+            fs::metadata(session_file).map_err(|e| CacheLoadError::Io(e.to_string()))?;
+        let live_size = metadata.len();
+        let live_mtime = metadata
+            .modified()
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+
+        let shrank = live_size < index.file_size;
+        let rewritten_in_place = live_size == index.file_size && live_mtime != index.last_modified;
+        let grew_without_advancing = live_size > index.file_size && live_mtime <= index.last_modified;
+
+        if shrank || rewritten_in_place || grew_without_advancing {
+            return Err(CacheLoadError::Stale);
+        }
+
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A cache whose `file_size` matches the live file exactly but whose
+    /// `last_modified` doesn't (e.g. an in-place rewrite that didn't change
+    /// the byte count) must be rejected, not treated as a trustworthy
+    /// append-only cache.
+    #[test]
+    fn load_rejects_same_size_different_mtime() {
+        let dir = std::env::temp_dir().join(format!(
+            "session-index-persistence-test-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let session_file = dir.join("session.jsonl");
+        let mut f = fs::File::create(&session_file).unwrap();
+        f.write_all(b"{\"type\":\"user\"}\n").unwrap();
+        drop(f);
+
+        let metadata = fs::metadata(&session_file).unwrap();
+        let mut index = SessionIndex::empty();
+        index.file_size = metadata.len();
+        index.last_modified = std::time::SystemTime::UNIX_EPOCH;
+
+        let cache_file = cache_path(&session_file);
+        index.save(&cache_file).unwrap();
+
+        let result = SessionIndex::load(&cache_file, &session_file);
+        assert!(matches!(result, Err(CacheLoadError::Stale)));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// A cache that's simply behind because the file grew must be accepted
+    /// by `load` and actually handed to `update_index_incremental` — not
+    /// just produce a correct final index (a full rebuild would too), but
+    /// go through the incremental merge. Verified by planting a marker in
+    /// the cached index that only survives if `update_index_incremental`
+    /// mutated the cached struct in place, rather than `build_session_index`
+    /// starting over from `SessionIndex::empty()`.
+    #[test]
+    fn load_accepts_growth_and_feeds_incremental_update() {
+        let dir = std::env::temp_dir().join(format!(
+            "session-index-persistence-test-growth-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let session_file = dir.join("session.jsonl");
+        let mut f = fs::File::create(&session_file).unwrap();
+        f.write_all(b"{\"type\":\"user\"}\n").unwrap();
+        drop(f);
+
+        let metadata = fs::metadata(&session_file).unwrap();
+        let mut index = SessionIndex::empty();
+        index.file_size = metadata.len();
+        // Deliberately far in the past rather than the file's real mtime, so
+        // "the live file is newer" holds regardless of filesystem mtime
+        // resolution.
+        index.last_modified = std::time::SystemTime::UNIX_EPOCH;
+        index
+            .hot_patterns
+            .insert("test-marker".to_string(), vec![999]);
+
+        let cache_file = cache_path(&session_file);
+        index.save(&cache_file).unwrap();
+
+        // The file grows (and its real mtime is necessarily after the
+        // epoch), simulating the common "reopen a session that was
+        // appended to" case.
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session_file)
+            .unwrap();
+        f.write_all(b"{\"type\":\"user\"}\n").unwrap();
+        drop(f);
+
+        let mut loaded = SessionIndex::load(&cache_file, &session_file)
+            .expect("a grown file with an older cache should still load");
+        assert!(loaded.hot_patterns.contains_key("test-marker"));
+
+        update_index_incremental(&mut loaded, &session_file, "/project").unwrap();
+        assert!(
+            loaded.hot_patterns.contains_key("test-marker"),
+            "update_index_incremental should merge into the cached index, not replace it"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
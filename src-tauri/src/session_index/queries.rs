@@ -2,12 +2,16 @@
 //!
 //! Provides efficient lookups using the pre-built index.
 
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::path::Path;
 
 use crate::claude_code::{parse_session_event, SessionEvent};
 
+use super::diff::{compute_diffs, FileDiff};
+use super::text_index::{searchable_segments, tokenize};
 use super::types::SessionIndex;
 
 /// Context for a file edit - all events from the triggering user message to the edit.
@@ -20,6 +24,8 @@ pub struct EditContext {
     pub trigger_line: u32,
     /// Line number of the edit itself
     pub edit_line: u32,
+    /// Structured diff hunks for every Edit/Write tool call in this range
+    pub diff: Vec<FileDiff>,
 }
 
 /// Get the context for a file edit.
@@ -29,6 +35,7 @@ pub struct EditContext {
 pub fn get_edit_context(
     index: &SessionIndex,
     session_file: &Path,
+    project_path: &str,
     edit_line: u32,
 ) -> Result<EditContext, String> {
     // Get the edit metadata
@@ -75,14 +82,234 @@ pub fn get_edit_context(
 
     // Load the actual events
     let events = load_events_for_lines(index, session_file, &lines_in_context)?;
+    let diff = compute_diffs(index, session_file, project_path, &lines_in_context)?;
 
     Ok(EditContext {
         events,
         trigger_line,
         edit_line,
+        diff,
     })
 }
 
+/// Return the line numbers of all events with `start <= timestamp <= end`
+/// (epoch millis), using two bisections over the sorted `timestamp_index`.
+pub fn lines_in_time_range(index: &SessionIndex, start: i64, end: i64) -> Vec<u32> {
+    let lo = index
+        .timestamp_index
+        .partition_point(|(ts, _)| *ts < start);
+    let hi = index.timestamp_index.partition_point(|(ts, _)| *ts <= end);
+    index.timestamp_index[lo..hi]
+        .iter()
+        .map(|(_, line)| *line)
+        .collect()
+}
+
+/// Find the line whose timestamp is closest to `t` (epoch millis).
+/// Ties prefer the earlier entry. Returns `None` if no entry was indexed.
+pub fn line_nearest_time(index: &SessionIndex, t: i64) -> Option<u32> {
+    if index.timestamp_index.is_empty() {
+        return None;
+    }
+
+    let pos = index.timestamp_index.partition_point(|(ts, _)| *ts < t);
+
+    let after = index.timestamp_index.get(pos);
+    let before = pos.checked_sub(1).and_then(|i| index.timestamp_index.get(i));
+
+    match (before, after) {
+        (Some((bts, bline)), Some((ats, aline))) => {
+            if (t - bts) <= (ats - t) {
+                Some(*bline)
+            } else {
+                Some(*aline)
+            }
+        }
+        (Some((_, bline)), None) => Some(*bline),
+        (None, Some((_, aline))) => Some(*aline),
+        (None, None) => None,
+    }
+}
+
+/// Number of characters of context to keep on each side of a match when
+/// building a [`SearchHit`] snippet.
+const SNIPPET_RADIUS: usize = 40;
+
+/// BM25 free parameters (standard defaults: term-frequency saturation and
+/// document-length normalization strength).
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// A single full-text search match, ranked by relevance.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Sequence number (line) this hit was found on.
+    pub line: u32,
+    /// Byte offset of the line, for jumping straight to it.
+    pub byte_offset: u64,
+    /// BM25 relevance score, summed across all matching query terms.
+    pub score: f64,
+    /// Which part of the entry matched (e.g. `user_message`, `tool_input_file_path`).
+    pub field: &'static str,
+    /// A short excerpt around the match.
+    pub snippet: String,
+}
+
+/// Search the index for `query`, returning up to `limit` lines ranked by a
+/// BM25 relevance score:
+///
+/// `score(line) = sum over query terms of idf * tf*(k1+1) / (tf + k1*(1 - b + b*len/avg_len))`
+///
+/// where `idf = ln((N - n + 0.5)/(n + 0.5) + 1)`, `tf` is the term's
+/// frequency on that line, `len`/`avg_len` are that line's and the
+/// session's average indexed token count, and `N`/`n` are the total
+/// indexed line count and the term's document frequency.
+///
+/// A multi-word query matches any line containing at least one term
+/// (ranking, not filtering, does the work of surfacing the best lines) —
+/// pass `match_all: true` to restore the stricter behavior of requiring
+/// every term to appear on the line.
+///
+/// Pass `human_only: true` to restrict hits to `human_message_lines`.
+pub fn search(
+    index: &SessionIndex,
+    session_file: &Path,
+    query: &str,
+    limit: usize,
+    human_only: bool,
+    match_all: bool,
+) -> Result<Vec<SearchHit>, String> {
+    let terms = tokenize(query);
+    if terms.is_empty() || index.text_doc_count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let n_docs = index.text_doc_count as f64;
+    let avg_len = (index.text_total_length as f64 / n_docs).max(1.0);
+
+    let mut scores: HashMap<u32, f64> = HashMap::new();
+    let mut matched_terms: HashMap<u32, u32> = HashMap::new();
+    for term in &terms {
+        let Some(postings) = index.inverted_index.get(term) else {
+            continue;
+        };
+        let n = postings.len() as f64;
+        let idf = ((n_docs - n + 0.5) / (n + 0.5) + 1.0).ln();
+
+        for &(line, tf) in postings {
+            if human_only && !index.is_human_message(line) {
+                continue;
+            }
+            let doc_len = *index.doc_lengths.get(&line).unwrap_or(&0) as f64;
+            let tf = tf as f64;
+            let denom = tf + BM25_K1 * (1.0 - BM25_B + BM25_B * doc_len / avg_len);
+            *scores.entry(line).or_insert(0.0) += idf * (tf * (BM25_K1 + 1.0)) / denom;
+            *matched_terms.entry(line).or_insert(0) += 1;
+        }
+    }
+
+    if match_all {
+        let required = terms.len() as u32;
+        scores.retain(|line, _| matched_terms.get(line).copied().unwrap_or(0) == required);
+    }
+
+    let mut ranked: Vec<(u32, f64)> = scores.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    ranked.truncate(limit);
+
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut hits = Vec::with_capacity(ranked.len());
+    for (line, score) in ranked {
+        let Some((offset, _)) = index.line_offsets.get(line as usize).copied() else {
+            continue;
+        };
+        let Some(raw) = read_raw_line(&mut file, offset)? else {
+            continue;
+        };
+        let Ok(entry) = serde_json::from_str::<Value>(&raw) else {
+            continue;
+        };
+        let Some(content) = entry.pointer("/message/content") else {
+            continue;
+        };
+        let entry_type = entry.get("type").and_then(Value::as_str);
+
+        let mut field = "message";
+        let mut snippet = String::new();
+        'segments: for segment in searchable_segments(entry_type, content) {
+            let lower = segment.text.to_lowercase();
+            for term in &terms {
+                if let Some(pos) = lower.find(term.as_str()) {
+                    field = segment.field;
+                    snippet = snippet_around(&segment.text, pos, term.len());
+                    break 'segments;
+                }
+            }
+        }
+
+        hits.push(SearchHit {
+            line,
+            byte_offset: offset,
+            score,
+            field,
+            snippet,
+        });
+    }
+
+    Ok(hits)
+}
+
+/// Read the raw (un-deserialized) JSON line at `offset`.
+fn read_raw_line(file: &mut File, offset: u64) -> Result<Option<String>, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut reader = BufReader::new(&*file);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read line: {}", e))?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+    }
+    if line.ends_with('\r') {
+        line.pop();
+    }
+    Ok(Some(line))
+}
+
+/// Build a snippet of `text` centered on a match at byte offset `pos` with
+/// length `match_len`, trimmed to `SNIPPET_RADIUS` characters of context.
+fn snippet_around(text: &str, pos: usize, match_len: usize) -> String {
+    let start = text[..pos]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_RADIUS - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let end_from = (pos + match_len).min(text.len());
+    let end = text[end_from..]
+        .char_indices()
+        .nth(SNIPPET_RADIUS)
+        .map(|(i, _)| end_from + i)
+        .unwrap_or(text.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("…");
+    }
+    snippet.push_str(text[start..end].trim());
+    if end < text.len() {
+        snippet.push_str("…");
+    }
+    snippet
+}
+
 /// Load SessionEvent objects for specific line numbers.
 fn load_events_for_lines(
     index: &SessionIndex,
@@ -131,3 +358,46 @@ fn read_event_at_offset(
 
     Ok(parse_session_event(&line, sequence, offset))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::build_session_index;
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    fn build_fixture(lines: &[&str]) -> (std::path::PathBuf, SessionIndex) {
+        let dir = std::env::temp_dir().join(format!(
+            "session-index-queries-test-{:?}",
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let session_file = dir.join("session.jsonl");
+        let mut f = fs::File::create(&session_file).unwrap();
+        for line in lines {
+            writeln!(f, "{}", line).unwrap();
+        }
+        drop(f);
+
+        let index = build_session_index(&session_file, "/project").unwrap();
+        (session_file, index)
+    }
+
+    #[test]
+    fn search_match_all_restricts_to_lines_with_every_term() {
+        let (session_file, index) = build_fixture(&[
+            r#"{"type":"user","userType":"external","message":{"content":"banana apple"}}"#,
+            r#"{"type":"user","userType":"external","message":{"content":"banana only"}}"#,
+        ]);
+
+        let any_term = search(&index, &session_file, "banana apple", 10, false, false).unwrap();
+        let lines: std::collections::HashSet<u32> = any_term.iter().map(|h| h.line).collect();
+        assert_eq!(lines, [0, 1].into_iter().collect());
+
+        let all_terms = search(&index, &session_file, "banana apple", 10, false, true).unwrap();
+        let lines: Vec<u32> = all_terms.iter().map(|h| h.line).collect();
+        assert_eq!(lines, vec![0]);
+
+        let _ = fs::remove_dir_all(session_file.parent().unwrap());
+    }
+}
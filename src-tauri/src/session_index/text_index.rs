@@ -0,0 +1,105 @@
+//! Tokenization and full-text indexing shared by the builder, the
+//! incremental updater, and search queries.
+//!
+//! Text is pulled from the fields users actually search: user and assistant
+//! message text (plain-string content and `text` blocks), and the
+//! `file_path` input of any tool call (so "which edit touched `config.rs`"
+//! works as a text search too).
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::types::SessionIndex;
+
+/// A labeled span of searchable text extracted from one entry.
+pub(crate) struct TextSegment {
+    pub field: &'static str,
+    pub text: String,
+}
+
+/// Split text into lowercase tokens on non-alphanumeric boundaries.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase())
+        .collect()
+}
+
+/// Extract the searchable text segments for an entry's `message.content`.
+pub(crate) fn searchable_segments(entry_type: Option<&str>, content: &Value) -> Vec<TextSegment> {
+    let field = match entry_type {
+        Some("user") => "user_message",
+        Some("assistant") => "assistant_message",
+        _ => "message",
+    };
+
+    let mut segments = Vec::new();
+    match content {
+        Value::String(s) => segments.push(TextSegment {
+            field,
+            text: s.clone(),
+        }),
+        Value::Array(items) => {
+            for item in items {
+                match item.get("type").and_then(Value::as_str) {
+                    Some("text") => {
+                        if let Some(t) = item.get("text").and_then(Value::as_str) {
+                            segments.push(TextSegment {
+                                field,
+                                text: t.to_string(),
+                            });
+                        }
+                    }
+                    Some("tool_use") => {
+                        if let Some(path) = item.pointer("/input/file_path").and_then(Value::as_str)
+                        {
+                            segments.push(TextSegment {
+                                field: "tool_input_file_path",
+                                text: path.to_string(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    segments
+}
+
+/// Merge the tokens from `segments` into the index's inverted index for
+/// `sequence`: one `(sequence, term_frequency)` posting per token, plus the
+/// line's total token count for BM25's document-length normalization.
+///
+/// Assumes `sequence` is monotonically increasing across calls (true for
+/// both the single-pass builder and tail-append incremental updates, and
+/// each sequence is only ever indexed once), so postings stay sorted
+/// without an explicit sort.
+pub(crate) fn index_line(index: &mut SessionIndex, sequence: u32, segments: &[TextSegment]) {
+    let mut term_frequencies: HashMap<String, u32> = HashMap::new();
+    let mut length: u32 = 0;
+
+    for segment in segments {
+        for token in tokenize(&segment.text) {
+            *term_frequencies.entry(token).or_insert(0) += 1;
+            length += 1;
+        }
+    }
+
+    if length == 0 {
+        return;
+    }
+
+    for (token, tf) in term_frequencies {
+        index
+            .inverted_index
+            .entry(token)
+            .or_default()
+            .push((sequence, tf));
+    }
+
+    index.doc_lengths.insert(sequence, length);
+    index.text_doc_count += 1;
+    index.text_total_length += length as u64;
+}
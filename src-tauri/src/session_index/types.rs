@@ -4,16 +4,16 @@
 //! which provides fast lookups into session JSONL files.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::SystemTime;
 
-use crate::claude_code::FileEdit;
+use crate::claude_code::{FileEdit, FileEditType};
 
 /// Index for a single session's JSONL file.
 ///
 /// Built once when a session is opened, updated incrementally on file changes.
 /// Provides O(1) lookups for UUIDs, file edits, and parent chain walking.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionIndex {
     // === File State (for incremental updates) ===
     /// Size of file when index was last built/updated
@@ -44,6 +44,45 @@ pub struct SessionIndex {
     /// file_path → sequence numbers of edits to that file
     pub file_to_edit_lines: HashMap<String, Vec<u32>>,
 
+    // === File Edit Classification State ===
+    // Retained (rather than discarded as local variables after the build
+    // pass) so an incremental tail-append update can re-run the exact same
+    // "added vs modified" classification as a full rebuild instead of
+    // reimplementing it against a partial view of just the new lines.
+    /// file_path → operation kind: `Modified` once any `Edit` has touched
+    /// the path, otherwise `Added` (first seen via `Write`)
+    pub file_operations: HashMap<String, FileEditType>,
+    /// Paths where some `Edit` in the session had a non-empty `old_string`,
+    /// i.e. the file had content before that edit touched it
+    pub files_with_prior_content: HashSet<String>,
+    /// file_path → timestamp of its most recent edit
+    pub file_timestamps: HashMap<String, String>,
+
+    // === Timestamp Index (for time-range queries) ===
+    /// (epoch_millis, line) pairs, sorted by epoch_millis, for every entry
+    /// that has a parseable RFC3339 `timestamp`. Entries without one are
+    /// excluded rather than indexed under a sentinel.
+    pub timestamp_index: Vec<(i64, u32)>,
+
+    // === Full-Text Search ===
+    /// Lowercased token → (sequence, term_frequency) pairs, sorted by
+    /// sequence, for every line that mentions it. Term frequency feeds the
+    /// BM25 ranking in `search`.
+    pub inverted_index: HashMap<String, Vec<(u32, u32)>>,
+    /// Sequence → number of indexed tokens on that line (BM25 document length).
+    pub doc_lengths: HashMap<u32, u32>,
+    /// Number of lines with indexed text, for BM25's average document length.
+    pub text_doc_count: u32,
+    /// Sum of `doc_lengths` values, for BM25's average document length.
+    pub text_total_length: u64,
+
+    // === Hot JSON Patterns ===
+    /// Pattern string → matching line numbers, pre-computed at index build
+    /// time for the small set of patterns in `pattern::HOT_PATTERNS` so
+    /// common queries (all thinking blocks, all tool errors) resolve in
+    /// O(1) instead of a line-by-line scan.
+    pub hot_patterns: HashMap<String, Vec<u32>>,
+
     // === Edit Metadata (for context feature) ===
     /// Sequence number → (byte_offset, messageId) for edits
     /// Allows looking up the message context for any edit
@@ -51,7 +90,7 @@ pub struct SessionIndex {
 }
 
 /// Metadata for a single file edit event.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditMetadata {
     /// UUID of this event (for parent chain walking)
     pub uuid: Option<String>,
@@ -85,7 +124,16 @@ impl SessionIndex {
             human_message_lines: Vec::new(),
             file_edits: Vec::new(),
             file_to_edit_lines: HashMap::new(),
+            file_operations: HashMap::new(),
+            files_with_prior_content: HashSet::new(),
+            file_timestamps: HashMap::new(),
             edit_metadata: HashMap::new(),
+            timestamp_index: Vec::new(),
+            inverted_index: HashMap::new(),
+            doc_lengths: HashMap::new(),
+            text_doc_count: 0,
+            text_total_length: 0,
+            hot_patterns: HashMap::new(),
         }
     }
 
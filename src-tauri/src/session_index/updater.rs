@@ -4,15 +4,14 @@
 
 use serde::Deserialize;
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::path::Path;
 
-use crate::claude_code::FileEditType;
-
-use super::builder::build_session_index;
-use super::types::{EditMetadata, SessionIndex};
+use super::builder::{build_session_index, finalize_file_edits, parse_timestamp_millis, process_tool_use};
+use super::pattern::index_hot_patterns;
+use super::text_index::{index_line, searchable_segments};
+use super::types::SessionIndex;
 
 /// Result of an incremental update.
 pub enum UpdateResult {
@@ -28,6 +27,10 @@ pub enum UpdateResult {
 ///
 /// If the file has grown (append-only), only parse new lines.
 /// If the file has shrunk or been modified, rebuild entirely.
+///
+/// Also available as [`update_session_index`], a name that emphasizes that
+/// it's a tail-append update rather than a full rebuild — the two are the
+/// same implementation, not two competing code paths.
 pub fn update_index_incremental(
     index: &mut SessionIndex,
     session_file: &Path,
@@ -50,6 +53,23 @@ pub fn update_index_incremental(
         return Ok(UpdateResult::Rebuilt);
     }
 
+    // Same size but a different mtime: an in-place rewrite rather than an
+    // append. There's nothing new to seek to and parse, but the bytes
+    // under the cached offsets may no longer be what they were, so rebuild
+    // rather than silently treating this as a no-op.
+    if current_size == index.file_size {
+        *index = build_session_index(session_file, project_path)?;
+        return Ok(UpdateResult::Rebuilt);
+    }
+
+    // current_size > index.file_size from here. A legitimate append always
+    // advances mtime; if it didn't, the metadata is inconsistent and the
+    // cached offsets can't be trusted as an unchanged prefix of this file.
+    if current_mtime <= index.last_modified {
+        *index = build_session_index(session_file, project_path)?;
+        return Ok(UpdateResult::Rebuilt);
+    }
+
     // File grew - incrementally parse new content
     let mut file = File::open(session_file)
         .map_err(|e| format!("Failed to open session file: {}", e))?;
@@ -62,11 +82,6 @@ pub fn update_index_incremental(
     let mut byte_offset = index.file_size;
     let start_sequence = index.line_offsets.len() as u32;
 
-    // Track new file edits
-    let mut new_file_operations: HashMap<String, FileEditType> = HashMap::new();
-    let mut new_files_with_prior_content: HashSet<String> = HashSet::new();
-    let mut new_file_timestamps: HashMap<String, String> = HashMap::new();
-
     for (rel_seq, line_result) in reader.lines().enumerate() {
         let line = match line_result {
             Ok(l) => l,
@@ -99,13 +114,40 @@ pub fn update_index_incremental(
                 }
             }
 
-            // Extract file edits from assistant messages
+            // Index the timestamp, if present and parseable. Appended
+            // timestamps are usually monotonic but not guaranteed, so
+            // insert in sorted-position rather than assuming append-at-end.
+            if let Some(ts) = entry.timestamp.as_deref().and_then(parse_timestamp_millis) {
+                let pos = index
+                    .timestamp_index
+                    .partition_point(|(existing, _)| *existing <= ts);
+                index.timestamp_index.insert(pos, (ts, sequence));
+            }
+
+            // Index searchable text for this line.
+            if let Some(ref message) = entry.message {
+                if let Some(ref content) = message.content {
+                    let segments = searchable_segments(entry.entry_type.as_deref(), content);
+                    index_line(index, sequence, &segments);
+                }
+            }
+
+            // Pre-index the small set of "hot" JSON patterns for O(1) lookup.
+            if let Ok(raw_value) = serde_json::from_str::<Value>(&line) {
+                index_hot_patterns(index, sequence, &raw_value);
+            }
+
+            // Extract file edits from assistant messages. This reuses the
+            // builder's own `process_tool_use`, which writes into `index`'s
+            // retained `file_operations`/`files_with_prior_content`/
+            // `file_timestamps` accumulators, so the classification below
+            // runs over the whole session's history, not just this batch.
             if entry.entry_type.as_deref() == Some("assistant") {
                 if let Some(ref message) = entry.message {
                     if let Some(ref content) = message.content {
                         if let Value::Array(items) = content {
                             for item in items {
-                                process_tool_use_incremental(
+                                process_tool_use(
                                     item,
                                     project_path,
                                     sequence,
@@ -114,9 +156,6 @@ pub fn update_index_incremental(
                                     entry.parent_uuid.as_deref(),
                                     entry.timestamp.as_deref(),
                                     index,
-                                    &mut new_file_operations,
-                                    &mut new_files_with_prior_content,
-                                    &mut new_file_timestamps,
                                 );
                             }
                         }
@@ -128,13 +167,9 @@ pub fn update_index_incremental(
         byte_offset += line_len as u64;
     }
 
-    // Merge new file edits into existing
-    merge_new_file_edits(
-        index,
-        new_file_operations,
-        new_files_with_prior_content,
-        new_file_timestamps,
-    );
+    // Recompute file_edits from the accumulators, which now reflect every
+    // Edit/Write seen since the session was opened, not just this append.
+    finalize_file_edits(index);
 
     // Update file state
     index.file_size = current_size;
@@ -143,6 +178,22 @@ pub fn update_index_incremental(
     Ok(UpdateResult::Updated)
 }
 
+/// Tail-append update: seek to the index's recorded `file_size`, parse only
+/// what was appended since, and merge it into the existing maps. Falls back
+/// to a full rebuild if the file was truncated, rewritten in place, or grew
+/// without its mtime advancing.
+///
+/// An alias for [`update_index_incremental`] — the name used when the
+/// caller wants to emphasize the tail-append behavior specifically (e.g. a
+/// live-tailing watcher) rather than "update somehow, I don't care how".
+pub fn update_session_index(
+    index: &mut SessionIndex,
+    session_file: &Path,
+    project_path: &str,
+) -> Result<UpdateResult, String> {
+    update_index_incremental(index, session_file, project_path)
+}
+
 /// Check if an entry is a human message.
 fn is_human_message(entry: &JsonEntry) -> bool {
     if entry.entry_type.as_deref() != Some("user") {
@@ -176,149 +227,6 @@ fn is_human_message(entry: &JsonEntry) -> bool {
     true
 }
 
-/// Process a tool_use for incremental updates.
-fn process_tool_use_incremental(
-    item: &Value,
-    project_path: &str,
-    sequence: u32,
-    _byte_offset: u64,
-    uuid: Option<&str>,
-    _parent_uuid: Option<&str>,
-    timestamp: Option<&str>,
-    index: &mut SessionIndex,
-    new_file_operations: &mut HashMap<String, FileEditType>,
-    new_files_with_prior_content: &mut HashSet<String>,
-    new_file_timestamps: &mut HashMap<String, String>,
-) {
-    if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
-        return;
-    }
-
-    let tool_name = match item.get("name").and_then(|v| v.as_str()) {
-        Some(n) => n,
-        None => return,
-    };
-
-    let input = match item.get("input") {
-        Some(i) => i,
-        None => return,
-    };
-
-    match tool_name {
-        "Edit" => {
-            if let Some(file_path) = input.get("file_path").and_then(|v| v.as_str()) {
-                let rel_path = make_relative_path(file_path, project_path);
-
-                if let Some(old_str) = input.get("old_string").and_then(|v| v.as_str()) {
-                    if !old_str.is_empty() {
-                        new_files_with_prior_content.insert(rel_path.clone());
-                    }
-                }
-
-                new_file_operations.insert(rel_path.clone(), FileEditType::Modified);
-
-                if let Some(ts) = timestamp {
-                    new_file_timestamps.insert(rel_path.clone(), ts.to_string());
-                }
-
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
-
-                index
-                    .file_to_edit_lines
-                    .entry(rel_path)
-                    .or_default()
-                    .push(sequence);
-            }
-        }
-        "Write" => {
-            if let Some(file_path) = input.get("file_path").and_then(|v| v.as_str()) {
-                let rel_path = make_relative_path(file_path, project_path);
-
-                // Check if this file already exists in the index
-                let file_exists = index.file_to_edit_lines.contains_key(&rel_path);
-                if !file_exists && !new_file_operations.contains_key(&rel_path) {
-                    new_file_operations.insert(rel_path.clone(), FileEditType::Added);
-                }
-
-                if let Some(ts) = timestamp {
-                    new_file_timestamps.insert(rel_path.clone(), ts.to_string());
-                }
-
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
-
-                index
-                    .file_to_edit_lines
-                    .entry(rel_path)
-                    .or_default()
-                    .push(sequence);
-            }
-        }
-        _ => {}
-    }
-}
-
-/// Merge new file edits into the existing index.
-fn merge_new_file_edits(
-    index: &mut SessionIndex,
-    new_file_operations: HashMap<String, FileEditType>,
-    new_files_with_prior_content: HashSet<String>,
-    new_file_timestamps: HashMap<String, String>,
-) {
-    use crate::claude_code::FileEdit;
-
-    for (path, edit_type) in new_file_operations {
-        // Find existing edit for this path
-        if let Some(existing) = index.file_edits.iter_mut().find(|e| e.path == path) {
-            // Update timestamp
-            if let Some(ts) = new_file_timestamps.get(&path) {
-                existing.last_edited_at = Some(ts.clone());
-            }
-            // If it was added before and now has prior content, it's modified
-            if new_files_with_prior_content.contains(&path) {
-                existing.edit_type = FileEditType::Modified;
-            }
-        } else {
-            // New file edit
-            let mut final_type = edit_type;
-            if final_type == FileEditType::Modified && !new_files_with_prior_content.contains(&path)
-            {
-                final_type = FileEditType::Added;
-            }
-
-            index.file_edits.push(FileEdit {
-                path: path.clone(),
-                edit_type: final_type,
-                last_edited_at: new_file_timestamps.get(&path).cloned(),
-            });
-        }
-    }
-
-    // Re-sort file edits
-    index.file_edits.sort_by(|a, b| a.path.cmp(&b.path));
-}
-
-/// Convert an absolute file path to a relative path from the project root.
-fn make_relative_path(file_path: &str, project_path: &str) -> String {
-    let project = project_path.trim_end_matches('/');
-    if file_path.starts_with(project) {
-        file_path[project.len()..]
-            .trim_start_matches('/')
-            .to_string()
-    } else {
-        file_path.to_string()
-    }
-}
-
 // === JSON Parsing Structures ===
 
 #[derive(Deserialize)]
@@ -342,3 +250,69 @@ struct JsonEntry {
 struct JsonMessage {
     content: Option<Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "session-index-updater-test-{}-{:?}",
+            name,
+            std::thread::current().id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Same byte count, different mtime (an in-place rewrite, e.g. a
+    /// compaction that rewrites earlier lines without changing the total
+    /// size) must trigger a full rebuild rather than being silently treated
+    /// as "nothing new to parse".
+    #[test]
+    fn same_size_different_mtime_rebuilds() {
+        let dir = scratch_dir("same-size");
+        let session_file = dir.join("session.jsonl");
+        fs::write(&session_file, b"{\"type\":\"user\"}\n").unwrap();
+
+        let mut index = build_session_index(&session_file, "/project").unwrap();
+        // The cached mtime no longer corresponds to the live file, even
+        // though the size hasn't changed.
+        index.last_modified = std::time::SystemTime::UNIX_EPOCH;
+
+        let result = update_index_incremental(&mut index, &session_file, "/project").unwrap();
+        assert!(matches!(result, UpdateResult::Rebuilt));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// A file that grew but whose mtime didn't advance past the cached
+    /// `last_modified` has inconsistent metadata — the cached offsets can't
+    /// be trusted as an unchanged prefix, so this must rebuild rather than
+    /// append from a possibly-wrong byte offset.
+    #[test]
+    fn grew_without_advancing_mtime_rebuilds() {
+        let dir = scratch_dir("grew-stale-mtime");
+        let session_file = dir.join("session.jsonl");
+        fs::write(&session_file, b"{\"type\":\"user\"}\n").unwrap();
+
+        let mut index = build_session_index(&session_file, "/project").unwrap();
+        // Pretend the cache is from the future relative to the live file's
+        // real mtime, so a real append still reads as "not advanced".
+        index.last_modified = std::time::SystemTime::now() + std::time::Duration::from_secs(1000);
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&session_file)
+            .unwrap();
+        f.write_all(b"{\"type\":\"user\"}\n").unwrap();
+        drop(f);
+
+        let result = update_index_incremental(&mut index, &session_file, "/project").unwrap();
+        assert!(matches!(result, UpdateResult::Rebuilt));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,185 @@
+//! Push-based index updates driven by filesystem events.
+//!
+//! `update_index_incremental` only advances when something calls it after
+//! noticing the session file changed. [`SessionWatcher`] closes that loop:
+//! it watches the session JSONL (and its enclosing project directory) with
+//! `notify`, debounces bursts of rapid appends into a single incremental
+//! update, and forwards the result to a callback so the frontend can
+//! live-tail an active session without polling.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::types::SessionIndex;
+use super::updater::{update_index_incremental, UpdateResult};
+
+/// How long to wait for more filesystem events before acting on a burst.
+/// Claude Code tends to write several JSONL lines in quick succession, so a
+/// naive "update on every event" approach would re-scan the tail repeatedly.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Fallback poll interval used when filesystem events don't arrive within
+/// a few debounce windows (e.g. network mounts where inotify is unreliable).
+const POLL_FALLBACK: Duration = Duration::from_secs(2);
+
+/// A notification emitted after the watcher drives an index update.
+pub struct WatchUpdate {
+    /// What kind of update was performed.
+    pub result: UpdateResult,
+    /// Line numbers newly added to the index by this update, `start..end`
+    /// (exclusive). Empty for [`UpdateResult::Unchanged`].
+    pub new_lines: std::ops::Range<u32>,
+}
+
+/// Watches a session file for changes and keeps a [`SessionIndex`] current.
+///
+/// Runs its own background thread; drop the watcher (or call
+/// [`SessionWatcher::stop`]) to stop watching.
+pub struct SessionWatcher {
+    handle: Option<JoinHandle<()>>,
+    stop_tx: std::sync::mpsc::Sender<()>,
+}
+
+impl SessionWatcher {
+    /// Start watching `session_file` for changes, invoking `on_update` from
+    /// the watcher's background thread whenever the index advances.
+    pub fn spawn(
+        session_file: PathBuf,
+        project_path: String,
+        mut index: SessionIndex,
+        on_update: impl Fn(&SessionIndex, WatchUpdate) + Send + 'static,
+    ) -> Result<Self, String> {
+        let (fs_tx, fs_rx) = channel::<notify::Result<Event>>();
+        let (stop_tx, stop_rx) = channel::<()>();
+
+        let mut watcher: RecommendedWatcher = notify::recommended_watcher(fs_tx)
+            .map_err(|e| format!("Failed to create filesystem watcher: {}", e))?;
+
+        watcher
+            .watch(&session_file, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch session file: {}", e))?;
+        if let Some(parent) = session_file.parent() {
+            // Catch rename/remove of the file itself, which some platforms
+            // only report against the containing directory.
+            let _ = watcher.watch(parent, RecursiveMode::NonRecursive);
+        }
+
+        let handle = std::thread::spawn(move || {
+            // Keep the watcher alive for the lifetime of the thread.
+            let _watcher = watcher;
+            let mut pending = false;
+            let mut last_event = Instant::now();
+
+            loop {
+                if stop_rx.try_recv().is_ok() {
+                    return;
+                }
+
+                match fs_rx.recv_timeout(DEBOUNCE) {
+                    Ok(Ok(event)) => {
+                        if event_is_relevant(&event, &session_file) {
+                            if is_destructive(&event.kind) {
+                                apply_rebuild(&session_file, &project_path, &mut index, &on_update);
+                                pending = false;
+                                continue;
+                            }
+                            pending = true;
+                            last_event = Instant::now();
+                        }
+                    }
+                    Ok(Err(_)) => {}
+                    Err(RecvTimeoutError::Timeout) => {
+                        // Either a debounced burst went quiet, or we fall
+                        // back to polling because events aren't arriving.
+                        if pending && last_event.elapsed() >= DEBOUNCE {
+                            apply_update(&session_file, &project_path, &mut index, &on_update);
+                            pending = false;
+                        } else if !pending && last_event.elapsed() >= POLL_FALLBACK {
+                            apply_update(&session_file, &project_path, &mut index, &on_update);
+                            last_event = Instant::now();
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+
+        Ok(Self {
+            handle: Some(handle),
+            stop_tx,
+        })
+    }
+
+    /// Stop watching and join the background thread.
+    pub fn stop(mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SessionWatcher {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+    }
+}
+
+fn event_is_relevant(event: &Event, session_file: &Path) -> bool {
+    event.paths.iter().any(|p| p == session_file)
+}
+
+fn is_destructive(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Remove(_) | EventKind::Modify(notify::event::ModifyKind::Name(_))
+    )
+}
+
+fn apply_update(
+    session_file: &Path,
+    project_path: &str,
+    index: &mut SessionIndex,
+    on_update: &impl Fn(&SessionIndex, WatchUpdate),
+) {
+    let start = index.total_events();
+    match update_index_incremental(index, session_file, project_path) {
+        Ok(UpdateResult::Unchanged) => {}
+        Ok(result) => {
+            let end = index.total_events();
+            let new_lines = if matches!(result, UpdateResult::Rebuilt) {
+                0..end
+            } else {
+                start..end
+            };
+            on_update(index, WatchUpdate { result, new_lines });
+        }
+        Err(_) => {
+            // Treat a failed incremental update as a forced rebuild.
+            apply_rebuild(session_file, project_path, index, on_update);
+        }
+    }
+}
+
+fn apply_rebuild(
+    session_file: &Path,
+    project_path: &str,
+    index: &mut SessionIndex,
+    on_update: &impl Fn(&SessionIndex, WatchUpdate),
+) {
+    if let Ok(rebuilt) = super::builder::build_session_index(session_file, project_path) {
+        let end = rebuilt.total_events();
+        *index = rebuilt;
+        on_update(
+            index,
+            WatchUpdate {
+                result: UpdateResult::Rebuilt,
+                new_lines: 0..end,
+            },
+        );
+    }
+}